@@ -0,0 +1,321 @@
+//! Decoders for the ADPCM-compressed `audio_format` variants this crate
+//! understands: Microsoft ADPCM (`WAVE_FORMAT_ADPCM`) and IMA/DVI ADPCM
+//! (`WAVE_FORMAT_DVI_ADPCM`). Both are exposed through the normal [`crate::read`]
+//! path, decoding straight into [`crate::BitDepth::Sixteen`].
+//!
+//! Since the last block of compressed `data` is padded to `block_align`,
+//! these decoders may produce a handful of trailing padding/decay samples
+//! past a file's true sample count. [`crate::read_with_chunks`] trims this
+//! using a `fact` chunk's sample count when one is present; the plain
+//! [`crate::read`] has no access to sibling chunks and can't.
+
+use std::convert::TryFrom;
+use std::io;
+
+use crate::{BitDepth, Header};
+
+/// The `WAVE_FORMAT_ADPCM` audio format tag (Microsoft ADPCM).
+pub const WAVE_FORMAT_ADPCM: u16 = 2;
+/// The `WAVE_FORMAT_DVI_ADPCM` audio format tag (IMA/DVI ADPCM).
+pub const WAVE_FORMAT_DVI_ADPCM: u16 = 0x11;
+
+/// The default Microsoft ADPCM coefficient table's `coef1` values, used
+/// since this crate doesn't parse a custom table out of the `fmt ` chunk.
+const MS_COEF1: [i32; 7] = [256, 512, 0, 192, 240, 460, 392];
+/// The default Microsoft ADPCM coefficient table's `coef2` values.
+const MS_COEF2: [i32; 7] = [0, -256, 0, 64, 0, -208, -232];
+/// The Microsoft ADPCM delta adaptation table.
+const MS_ADAPT: [i32; 16] = [
+    230, 230, 230, 230, 307, 409, 512, 614, 768, 614, 512, 409, 307, 230, 230, 230,
+];
+
+/// The IMA ADPCM step-size table.
+const IMA_STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408,
+    449, 494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066,
+    2272, 2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630,
+    9493, 10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794,
+    32767,
+];
+/// The IMA ADPCM step index adjustment table.
+const IMA_INDEX_TABLE: [i32; 8] = [-1, -1, -1, -1, 2, 4, 6, 8];
+
+/// Decodes a Microsoft ADPCM (`audio_format == 2`) `data` chunk into
+/// `BitDepth::Sixteen`.
+///
+/// Uses the default 7-pair coefficient table rather than one read from the
+/// `fmt ` chunk, since that covers every encoder actually seen in the wild.
+///
+/// ## Errors
+///
+/// This function fails if `header` is missing `samples_per_block`, a block
+/// header names a predictor index outside the coefficient table, or `data`
+/// doesn't hold enough bytes for a block header.
+pub fn decode_ms(header: &Header, data: &[u8]) -> io::Result<BitDepth> {
+    let channels = usize::from(header.channel_count);
+    let samples_per_block = usize::from(header.samples_per_block.ok_or_else(|| {
+        io::Error::other("MS ADPCM \"fmt \" chunk is missing wSamplesPerBlock")
+    })?);
+    let block_align = usize::from(header.bytes_per_sample);
+
+    if channels == 0 || block_align == 0 {
+        return Err(io::Error::other("Invalid MS ADPCM block alignment"));
+    }
+
+    let mut out = Vec::with_capacity(samples_per_block * channels * data.len().div_ceil(block_align.max(1)));
+
+    for block in data.chunks(block_align) {
+        decode_ms_block(block, channels, samples_per_block, &mut out)?;
+    }
+
+    Ok(BitDepth::Sixteen(out))
+}
+
+fn decode_ms_block(
+    block: &[u8],
+    channels: usize,
+    samples_per_block: usize,
+    out: &mut Vec<i16>,
+) -> io::Result<()> {
+    if block.len() < channels * 7 {
+        return Err(io::Error::other("MS ADPCM block is too short for its header"));
+    }
+
+    let mut predictor_idx = vec![0usize; channels];
+    let mut delta = vec![0i32; channels];
+    let mut sample1 = vec![0i32; channels];
+    let mut sample2 = vec![0i32; channels];
+
+    let mut pos = 0;
+    for idx in &mut predictor_idx {
+        *idx = usize::from(block[pos]);
+        if *idx >= MS_COEF1.len() {
+            return Err(io::Error::other("MS ADPCM predictor index out of range"));
+        }
+        pos += 1;
+    }
+    for d in &mut delta {
+        *d = i32::from(i16::from_le_bytes([block[pos], block[pos + 1]]));
+        pos += 2;
+    }
+    for s in &mut sample1 {
+        *s = i32::from(i16::from_le_bytes([block[pos], block[pos + 1]]));
+        pos += 2;
+    }
+    for s in &mut sample2 {
+        *s = i32::from(i16::from_le_bytes([block[pos], block[pos + 1]]));
+        pos += 2;
+    }
+
+    for &s in &sample2 {
+        out.push(clamp_i16(s));
+    }
+    for &s in &sample1 {
+        out.push(clamp_i16(s));
+    }
+
+    let mut channel = 0;
+    let mut samples_decoded = 2;
+    'nibbles: for &byte in &block[pos..] {
+        for nibble in [byte >> 4, byte & 0x0F] {
+            if samples_decoded >= samples_per_block {
+                break 'nibbles;
+            }
+
+            let coef1 = MS_COEF1[predictor_idx[channel]];
+            let coef2 = MS_COEF2[predictor_idx[channel]];
+            let predict = (sample1[channel] * coef1 + sample2[channel] * coef2) >> 8;
+            let signed = if nibble >= 8 {
+                i32::from(nibble) - 16
+            } else {
+                i32::from(nibble)
+            };
+            let new_sample = clamp_i16(predict + delta[channel] * signed);
+
+            sample2[channel] = sample1[channel];
+            sample1[channel] = i32::from(new_sample);
+            delta[channel] = ((delta[channel] * MS_ADAPT[usize::from(nibble)]) >> 8).max(16);
+
+            out.push(new_sample);
+
+            channel += 1;
+            if channel == channels {
+                channel = 0;
+                samples_decoded += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Decodes an IMA/DVI ADPCM (`audio_format == 0x11`) `data` chunk into
+/// `BitDepth::Sixteen`.
+///
+/// ## Errors
+///
+/// This function fails if `header`'s block alignment is zero, or `data`
+/// doesn't hold enough bytes for a block header.
+pub fn decode_ima(header: &Header, data: &[u8]) -> io::Result<BitDepth> {
+    let channels = usize::from(header.channel_count);
+    let block_align = usize::from(header.bytes_per_sample);
+
+    if channels == 0 || block_align == 0 {
+        return Err(io::Error::other("Invalid IMA ADPCM block alignment"));
+    }
+
+    let mut out = Vec::new();
+
+    for block in data.chunks(block_align) {
+        decode_ima_block(block, channels, &mut out)?;
+    }
+
+    Ok(BitDepth::Sixteen(out))
+}
+
+fn decode_ima_block(block: &[u8], channels: usize, out: &mut Vec<i16>) -> io::Result<()> {
+    if block.len() < channels * 4 {
+        return Err(io::Error::other("IMA ADPCM block is too short for its header"));
+    }
+
+    let mut predictor = vec![0i32; channels];
+    let mut index = vec![0i32; channels];
+
+    for channel in 0..channels {
+        let base = channel * 4;
+        predictor[channel] = i32::from(i16::from_le_bytes([block[base], block[base + 1]]));
+        index[channel] = i32::from(block[base + 2]).clamp(0, 88);
+        out.push(clamp_i16(predictor[channel]));
+    }
+
+    let body = &block[channels * 4..];
+
+    // Nibbles are packed in 4-byte (8-nibble) groups per channel, but each
+    // channel's group of 8 samples must land interleaved with the other
+    // channels' in `out`, so decode a group into per-channel scratch buffers
+    // first and interleave them afterwards.
+    let mut group_samples = vec![Vec::with_capacity(8); channels];
+    for group in body.chunks(4 * channels) {
+        for buf in &mut group_samples {
+            buf.clear();
+        }
+
+        for (channel, chunk) in group.chunks(4).enumerate() {
+            for &byte in chunk {
+                for nibble in [byte & 0x0F, byte >> 4] {
+                    decode_ima_nibble(nibble, &mut predictor[channel], &mut index[channel], &mut group_samples[channel]);
+                }
+            }
+        }
+
+        let frames = group_samples.iter().map(Vec::len).min().unwrap_or(0);
+        for frame in 0..frames {
+            for buf in &group_samples {
+                out.push(buf[frame]);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn decode_ima_nibble(nibble: u8, predictor: &mut i32, index: &mut i32, out: &mut Vec<i16>) {
+    let step = IMA_STEP_TABLE[usize::try_from(*index).unwrap_or(0)];
+    let n = i32::from(nibble);
+
+    let mut diff = step >> 3;
+    if n & 4 != 0 {
+        diff += step;
+    }
+    if n & 2 != 0 {
+        diff += step >> 1;
+    }
+    if n & 1 != 0 {
+        diff += step >> 2;
+    }
+
+    if n & 8 != 0 {
+        *predictor -= diff;
+    } else {
+        *predictor += diff;
+    }
+    *predictor = i32::from(clamp_i16(*predictor));
+
+    *index = (*index + IMA_INDEX_TABLE[usize::try_from(n & 7).unwrap_or(0)]).clamp(0, 88);
+
+    out.push(clamp_i16(*predictor));
+}
+
+fn clamp_i16(v: i32) -> i16 {
+    i16::try_from(v.clamp(i32::from(i16::MIN), i32::from(i16::MAX))).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_ms_matches_hand_computed_block() {
+        // predictor_idx = 0 (coef1 = 256, coef2 = 0), delta = 16,
+        // sample1 = 100, sample2 = 50, then one nibble byte (high nibble
+        // first): 0x3 then 0x9.
+        let block = [0x00, 0x10, 0x00, 0x64, 0x00, 0x32, 0x00, 0x39];
+
+        let header = Header {
+            audio_format: WAVE_FORMAT_ADPCM,
+            channel_count: 1,
+            sampling_rate: 8_000,
+            bytes_per_second: 8_000 * 8,
+            bytes_per_sample: 8,
+            bits_per_sample: 4,
+            extension: None,
+            samples_per_block: Some(4),
+        };
+
+        // predict = (100*256 + 50*0) >> 8 = 100; new = 100 + 16*3 = 148
+        // predict = (148*256 + 100*0) >> 8 = 148; new = 148 + 16*-7 = 36
+        let BitDepth::Sixteen(samples) = decode_ms(&header, &block).unwrap() else {
+            panic!("decode_ms should produce BitDepth::Sixteen");
+        };
+        assert_eq!(samples, vec![50, 100, 148, 36]);
+    }
+
+    #[test]
+    fn decode_ima_block_interleaves_channels() {
+        // Two channels, one 4-byte predictor header each, followed by one
+        // 8-byte nibble group (4 bytes per channel).
+        let block = [
+            // channel 0 header: predictor = 0, step index = 0, reserved = 0
+            0x00, 0x00, 0x00, 0x00,
+            // channel 1 header: predictor = 0, step index = 0, reserved = 0
+            0x00, 0x00, 0x00, 0x00,
+            // channel 0 nibbles, all 0x6 (distinct from channel 1's pattern)
+            0x66, 0x66, 0x66, 0x66,
+            // channel 1 nibbles, all 0xE
+            0xEE, 0xEE, 0xEE, 0xEE,
+        ];
+
+        let mut expected_ch0 = Vec::new();
+        let (mut predictor0, mut index0) = (0, 0);
+        for _ in 0..8 {
+            decode_ima_nibble(0x6, &mut predictor0, &mut index0, &mut expected_ch0);
+        }
+
+        let mut expected_ch1 = Vec::new();
+        let (mut predictor1, mut index1) = (0, 0);
+        for _ in 0..8 {
+            decode_ima_nibble(0xE, &mut predictor1, &mut index1, &mut expected_ch1);
+        }
+        assert_ne!(expected_ch0, expected_ch1, "fixture should exercise genuinely different channels");
+
+        let mut out = Vec::new();
+        decode_ima_block(&block, 2, &mut out).unwrap();
+
+        assert_eq!(&out[0..2], &[0, 0], "initial predictors for both channels");
+        for i in 0..8 {
+            assert_eq!(out[2 + i * 2], expected_ch0[i], "channel 0 sample {i}");
+            assert_eq!(out[2 + i * 2 + 1], expected_ch1[i], "channel 1 sample {i}");
+        }
+    }
+}
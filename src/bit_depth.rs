@@ -0,0 +1,429 @@
+//! Contains the `BitDepth` enum, used to store audio data of varying
+//! bit-depths and sample formats.
+
+use std::convert::TryFrom;
+
+/// Represents the bit depth and sample format of the audio data, with the
+/// audio data bundled in.
+///
+/// Any of the non-empty variants may be used regardless of number of
+/// channels. So stereo audio using 16 bits per sample would still use the
+/// `Sixteen` variant, and would be interleaved.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BitDepth {
+    /// Audio data is encoded as `u8` within this variant.
+    Eight(Vec<u8>),
+    /// Audio data is encoded as `i16` within this variant.
+    Sixteen(Vec<i16>),
+    /// Audio data is encoded as `i32` within this variant, despite being
+    /// limited to 24 bits. This is due to there not being a native 24-bit
+    /// word size within Rust.
+    TwentyFour(Vec<i32>),
+    /// Audio data is encoded as `i32` within this variant.
+    ThirtyTwo(Vec<i32>),
+    /// Audio data is encoded as IEEE 754 single-precision floats within
+    /// this variant, used for `WAVE_FORMAT_IEEE_FLOAT` data with a
+    /// `bits_per_sample` of 32.
+    ThirtyTwoFloat(Vec<f32>),
+    /// Audio data is encoded as IEEE 754 double-precision floats within
+    /// this variant, used for `WAVE_FORMAT_IEEE_FLOAT` data with a
+    /// `bits_per_sample` of 64.
+    SixtyFourFloat(Vec<f64>),
+    /// Variant representing that there is no audio data present. This is
+    /// used as the default for when `BitDepth` is used in a default
+    /// context.
+    Empty,
+}
+
+impl Default for BitDepth {
+    /// The default `BitDepth` is the `Empty` variant, representing no
+    /// audio samples.
+    fn default() -> Self {
+        BitDepth::Empty
+    }
+}
+
+impl From<Vec<u8>> for BitDepth {
+    fn from(v: Vec<u8>) -> Self {
+        BitDepth::Eight(v)
+    }
+}
+
+impl From<Vec<i16>> for BitDepth {
+    fn from(v: Vec<i16>) -> Self {
+        BitDepth::Sixteen(v)
+    }
+}
+
+impl From<Vec<i32>> for BitDepth {
+    fn from(v: Vec<i32>) -> Self {
+        BitDepth::TwentyFour(v)
+    }
+}
+
+impl From<Vec<f32>> for BitDepth {
+    fn from(v: Vec<f32>) -> Self {
+        BitDepth::ThirtyTwoFloat(v)
+    }
+}
+
+impl From<Vec<f64>> for BitDepth {
+    fn from(v: Vec<f64>) -> Self {
+        BitDepth::SixtyFourFloat(v)
+    }
+}
+
+/// The number of samples considered on each side of the windowed-sinc
+/// kernel used by [`BitDepth::resample`]. Larger values trade performance
+/// for a sharper, more accurate low-pass cutoff.
+const RESAMPLE_HALF_WIDTH: f64 = 8.0;
+
+impl BitDepth {
+    /// Converts this track's samples to normalized `f64`s in the range
+    /// `-1.0..=1.0`, regardless of the original bit depth or sample format.
+    fn to_f64(&self) -> Vec<f64> {
+        match self {
+            BitDepth::Eight(v) => v.iter().map(|&s| (f64::from(s) - 128.0) / 128.0).collect(),
+            BitDepth::Sixteen(v) => v.iter().map(|&s| f64::from(s) / 32_768.0).collect(),
+            BitDepth::TwentyFour(v) | BitDepth::ThirtyTwo(v) => {
+                v.iter().map(|&s| f64::from(s) / 2_147_483_648.0).collect()
+            }
+            BitDepth::ThirtyTwoFloat(v) => v.iter().map(|&s| f64::from(s)).collect(),
+            BitDepth::SixtyFourFloat(v) => v.clone(),
+            BitDepth::Empty => Vec::new(),
+        }
+    }
+
+    /// Rebuilds a `BitDepth` of the same variant as `self` out of normalized
+    /// `-1.0..=1.0` samples, as produced by [`BitDepth::to_f64`].
+    fn with_samples(&self, values: &[f64]) -> BitDepth {
+        match self {
+            BitDepth::Eight(_) => BitDepth::Eight(values.iter().map(|&v| f64_to_eight(v)).collect()),
+            BitDepth::Sixteen(_) => BitDepth::Sixteen(values.iter().map(|&v| f64_to_sixteen(v)).collect()),
+            BitDepth::TwentyFour(_) => {
+                BitDepth::TwentyFour(values.iter().map(|&v| f64_to_thirty_two(v)).collect())
+            }
+            BitDepth::ThirtyTwo(_) => BitDepth::ThirtyTwo(values.iter().map(|&v| f64_to_thirty_two(v)).collect()),
+            BitDepth::ThirtyTwoFloat(_) => {
+                BitDepth::ThirtyTwoFloat(values.iter().map(|&v| f64_to_f32(f64_to_float(v))).collect())
+            }
+            BitDepth::SixtyFourFloat(_) => {
+                BitDepth::SixtyFourFloat(values.iter().map(|&v| f64_to_float(v)).collect())
+            }
+            BitDepth::Empty => BitDepth::Empty,
+        }
+    }
+
+    /// The number of bits of precision this variant's samples carry, used to
+    /// decide when [`BitDepth::to_sixteen`] and [`BitDepth::to_twenty_four`]
+    /// are narrowing (and so need to dither) versus simply widening.
+    fn source_bits(&self) -> u32 {
+        match self {
+            BitDepth::Eight(_) => 8,
+            BitDepth::Sixteen(_) => 16,
+            BitDepth::TwentyFour(_) => 24,
+            BitDepth::ThirtyTwo(_) | BitDepth::ThirtyTwoFloat(_) => 32,
+            BitDepth::SixtyFourFloat(_) => 64,
+            BitDepth::Empty => 0,
+        }
+    }
+
+    /// Converts this track to 16-bit signed PCM, scaling between depths as
+    /// necessary.
+    ///
+    /// ## Notes
+    ///
+    /// Narrowing to a lower bit depth loses precision; when narrowing, a
+    /// triangular dither is added before rounding to decorrelate the
+    /// resulting quantization error from the signal.
+    #[must_use]
+    pub fn to_sixteen(&self) -> BitDepth {
+        match self {
+            BitDepth::Sixteen(_) => self.clone(),
+            _ if self.source_bits() > 16 => {
+                let mut rng = DitherRng::new();
+                BitDepth::Sixteen(
+                    self.to_f64()
+                        .iter()
+                        .map(|&v| f64_to_sixteen_dithered(v, &mut rng))
+                        .collect(),
+                )
+            }
+            _ => BitDepth::Sixteen(self.to_f64().iter().map(|&v| f64_to_sixteen(v)).collect()),
+        }
+    }
+
+    /// Converts this track to 24-bit signed PCM (stored widened in `i32`,
+    /// like the rest of the crate), scaling between depths as necessary.
+    ///
+    /// ## Notes
+    ///
+    /// Narrowing to a lower bit depth loses precision; when narrowing, a
+    /// triangular dither is added before rounding to decorrelate the
+    /// resulting quantization error from the signal.
+    #[must_use]
+    pub fn to_twenty_four(&self) -> BitDepth {
+        match self {
+            BitDepth::TwentyFour(_) => self.clone(),
+            _ if self.source_bits() > 24 => {
+                let mut rng = DitherRng::new();
+                BitDepth::TwentyFour(
+                    self.to_f64()
+                        .iter()
+                        .map(|&v| f64_to_twenty_four_dithered(v, &mut rng))
+                        .collect(),
+                )
+            }
+            _ => BitDepth::TwentyFour(self.to_f64().iter().map(|&v| f64_to_thirty_two(v)).collect()),
+        }
+    }
+
+    /// Converts this track to 32-bit IEEE float, scaling as necessary.
+    #[must_use]
+    pub fn to_float(&self) -> BitDepth {
+        match self {
+            BitDepth::ThirtyTwoFloat(_) => self.clone(),
+            _ => BitDepth::ThirtyTwoFloat(self.to_f64().iter().map(|&v| f64_to_f32(f64_to_float(v))).collect()),
+        }
+    }
+
+    /// Resamples this track from `from_hz` to `to_hz` using windowed-sinc
+    /// (band-limited) interpolation, preserving its current `BitDepth`
+    /// variant.
+    ///
+    /// ## Notes
+    ///
+    /// This resamples the raw sample stream as given; for interleaved
+    /// multi-channel audio, de-interleave into one stream per channel
+    /// first, or samples from different channels will bleed into one
+    /// another.
+    #[must_use]
+    pub fn resample(&self, from_hz: u32, to_hz: u32) -> BitDepth {
+        if from_hz == 0 || to_hz == 0 || from_hz == to_hz || matches!(self, BitDepth::Empty) {
+            return self.clone();
+        }
+
+        self.with_samples(&windowed_sinc_resample(&self.to_f64(), from_hz, to_hz))
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn f64_to_eight(v: f64) -> u8 {
+    (v * 128.0 + 128.0).round().clamp(0.0, 255.0) as u8
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn f64_to_sixteen(v: f64) -> i16 {
+    (v * 32_768.0).round().clamp(f64::from(i16::MIN), f64::from(i16::MAX)) as i16
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn f64_to_thirty_two(v: f64) -> i32 {
+    (v * 2_147_483_648.0).round().clamp(f64::from(i32::MIN), f64::from(i32::MAX)) as i32
+}
+
+/// A small xorshift64* PRNG, used only to generate dithering noise. Not
+/// intended to be cryptographically secure, just fast and unbiased enough
+/// for that purpose; seeded with a fixed constant so conversions stay
+/// deterministic.
+struct DitherRng(u64);
+
+impl DitherRng {
+    fn new() -> Self {
+        DitherRng(0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A sample in `-0.5..0.5`, uniformly distributed.
+    #[allow(clippy::cast_precision_loss)]
+    fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+    }
+
+    /// A sample in `-1.0..1.0`, triangularly distributed (the sum of two
+    /// independent uniform samples), as used for TPDF dither.
+    fn triangular(&mut self) -> f64 {
+        self.uniform() + self.uniform()
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn f64_to_sixteen_dithered(v: f64, rng: &mut DitherRng) -> i16 {
+    (v * 32_768.0 + rng.triangular())
+        .round()
+        .clamp(f64::from(i16::MIN), f64::from(i16::MAX)) as i16
+}
+
+/// Like [`f64_to_thirty_two`], but dithers by one 24-bit quantization step
+/// (`2^8`, since 24-bit samples are stored left-shifted by 8 bits within the
+/// `i32`) before rounding.
+#[allow(clippy::cast_possible_truncation)]
+fn f64_to_twenty_four_dithered(v: f64, rng: &mut DitherRng) -> i32 {
+    (v * 2_147_483_648.0 + rng.triangular() * 256.0)
+        .round()
+        .clamp(f64::from(i32::MIN), f64::from(i32::MAX)) as i32
+}
+
+fn f64_to_float(v: f64) -> f64 {
+    v.clamp(-1.0, 1.0)
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn f64_to_f32(v: f64) -> f32 {
+    v as f32
+}
+
+/// The value of `sin(pi * x) / (pi * x)`, or `1.0` at `x == 0.0`.
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        1.0
+    } else {
+        let px = std::f64::consts::PI * x;
+        px.sin() / px
+    }
+}
+
+/// A Blackman window over `x` in `-half_width..=half_width`, used to taper
+/// the sinc kernel to a finite width.
+fn blackman_window(x: f64, half_width: f64) -> f64 {
+    let n = (x + half_width) / (2.0 * half_width);
+    0.42 - 0.5 * (2.0 * std::f64::consts::PI * n).cos() + 0.08 * (4.0 * std::f64::consts::PI * n).cos()
+}
+
+/// Resamples normalized `samples` from `from_hz` to `to_hz` with a
+/// windowed-sinc kernel, widening the kernel (lowering its cutoff) when
+/// downsampling to keep the result band-limited.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn windowed_sinc_resample(samples: &[f64], from_hz: u32, to_hz: u32) -> Vec<f64> {
+    if samples.is_empty() {
+        return Vec::new();
+    }
+
+    let ratio = f64::from(to_hz) / f64::from(from_hz);
+    let out_len = ((samples.len() as f64) * ratio).round().max(0.0) as usize;
+    let cutoff_scale = ratio.min(1.0);
+
+    let mut out = Vec::with_capacity(out_len);
+
+    for i in 0..out_len {
+        let src_pos = i as f64 / ratio;
+        let span = RESAMPLE_HALF_WIDTH / cutoff_scale;
+        let lo = (src_pos - span).floor() as i64;
+        let hi = (src_pos + span).ceil() as i64;
+
+        let mut acc = 0.0;
+        let mut weight_sum = 0.0;
+        for j in lo..=hi {
+            let Some(&sample) = usize::try_from(j).ok().and_then(|j| samples.get(j)) else {
+                continue;
+            };
+
+            let dx = (src_pos - j as f64) * cutoff_scale;
+            if dx.abs() > RESAMPLE_HALF_WIDTH {
+                continue;
+            }
+
+            let weight = sinc(dx) * blackman_window(dx, RESAMPLE_HALF_WIDTH);
+            acc += sample * weight;
+            weight_sum += weight;
+        }
+
+        out.push(if weight_sum.abs() > 1e-9 { acc / weight_sum } else { 0.0 });
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_sixteen_scales_eight_bit_samples() {
+        let BitDepth::Sixteen(samples) = BitDepth::Eight(vec![0, 128, 255]).to_sixteen() else {
+            panic!("to_sixteen should produce BitDepth::Sixteen");
+        };
+        assert_eq!(samples, vec![-32_768, 0, 32_512]);
+    }
+
+    #[test]
+    fn to_sixteen_is_a_cheap_clone_for_already_sixteen_data() {
+        let track = BitDepth::Sixteen(vec![1, -2, 3]);
+        assert_eq!(track.to_sixteen(), track);
+    }
+
+    #[test]
+    fn to_twenty_four_widens_sixteen_bit_samples() {
+        let BitDepth::TwentyFour(samples) = BitDepth::Sixteen(vec![0, 16_384, -16_384]).to_twenty_four() else {
+            panic!("to_twenty_four should produce BitDepth::TwentyFour");
+        };
+        assert_eq!(samples, vec![0, 1_073_741_824, -1_073_741_824]);
+    }
+
+    #[test]
+    fn to_sixteen_dithers_when_narrowing_from_a_higher_bit_depth() {
+        // A constant full-scale signal would quantize to the same i16 value
+        // every sample without dithering; with dithering added before
+        // rounding, at least one sample should be nudged to a neighboring
+        // value instead.
+        let track = BitDepth::ThirtyTwo(vec![1_073_741_824; 64]); // 0.5 normalized
+        let BitDepth::Sixteen(samples) = track.to_sixteen() else {
+            panic!("to_sixteen should produce BitDepth::Sixteen");
+        };
+
+        assert!(samples.iter().all(|&s| (s - 16_384).abs() <= 1));
+        assert!(samples.iter().any(|&s| s != 16_384));
+    }
+
+    #[test]
+    fn to_twenty_four_dithers_when_narrowing_from_a_higher_bit_depth() {
+        let track = BitDepth::ThirtyTwo(vec![1_073_741_824; 64]); // 0.5 normalized
+        let BitDepth::TwentyFour(samples) = track.to_twenty_four() else {
+            panic!("to_twenty_four should produce BitDepth::TwentyFour");
+        };
+
+        assert!(samples.iter().all(|&s| (s - 1_073_741_824).abs() <= 256));
+        assert!(samples.iter().any(|&s| s != 1_073_741_824));
+    }
+
+    #[test]
+    fn to_float_normalizes_sixteen_bit_samples() {
+        let BitDepth::ThirtyTwoFloat(samples) = BitDepth::Sixteen(vec![0, 16_384, -32_768]).to_float() else {
+            panic!("to_float should produce BitDepth::ThirtyTwoFloat");
+        };
+        assert_eq!(samples, vec![0.0, 0.5, -1.0]);
+    }
+
+    #[test]
+    fn resample_is_a_no_op_for_matching_rates() {
+        let track = BitDepth::Sixteen(vec![1, 2, 3]);
+        assert_eq!(track.resample(44_100, 44_100), track);
+    }
+
+    #[test]
+    fn resample_preserves_a_constant_signal_and_scales_length() {
+        let track = BitDepth::Sixteen(vec![1_000; 20]);
+        let BitDepth::Sixteen(resampled) = track.resample(8_000, 16_000) else {
+            panic!("resample should preserve the BitDepth::Sixteen variant");
+        };
+
+        assert_eq!(resampled.len(), 40);
+        // Away from the kernel's edges, a DC signal should resample back to
+        // itself almost exactly.
+        for &sample in &resampled[10..30] {
+            assert!((i32::from(sample) - 1_000).abs() <= 1, "sample {sample} drifted from the constant input", sample = sample);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Typed helpers for the auxiliary chunks returned by
+//! [`crate::read_with_chunks`] and accepted by [`crate::write_with_chunks`].
+//!
+//! These chunks are kept around as raw `(riff::ChunkId, Vec<u8>)` pairs, so
+//! that any chunk not understood by this module still survives a
+//! read/write round-trip untouched. This module just adds a convenient way
+//! to read and set the common ones.
+
+use std::convert::TryFrom;
+
+/// The common `LIST`/`INFO` textual metadata tags.
+///
+/// Each field corresponds to a four-character `INFO` subchunk id; fields
+/// left as `None` are omitted entirely when the tags are turned back into
+/// a chunk via [`InfoTags::into_chunk`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InfoTags {
+    /// The `IART` tag: the artist or performer.
+    pub artist: Option<String>,
+    /// The `INAM` tag: the title of the work.
+    pub title: Option<String>,
+    /// The `ICMT` tag: a free-form comment.
+    pub comment: Option<String>,
+}
+
+impl InfoTags {
+    /// Scans `chunks` (as returned by [`crate::read_with_chunks`]) for a
+    /// `LIST`/`INFO` chunk and extracts the tags it recognizes.
+    ///
+    /// If no `LIST`/`INFO` chunk is present, all fields are `None`.
+    #[must_use]
+    pub fn from_chunks(chunks: &[crate::ExtraChunk]) -> Self {
+        let mut tags = InfoTags::default();
+
+        for (id, data) in chunks {
+            if id.as_str() != "LIST" || data.len() < 4 || &data[0..4] != b"INFO" {
+                continue;
+            }
+
+            for (tag_id, tag_data) in iter_sub_chunks(&data[4..]) {
+                let text = String::from_utf8_lossy(strip_nul(tag_data)).into_owned();
+                match tag_id.as_str() {
+                    "IART" => tags.artist = Some(text),
+                    "INAM" => tags.title = Some(text),
+                    "ICMT" => tags.comment = Some(text),
+                    _ => {}
+                }
+            }
+        }
+
+        tags
+    }
+
+    /// Builds a `LIST`/`INFO` chunk out of the populated fields, or
+    /// `None` if every field is unset.
+    ///
+    /// ## Panics
+    ///
+    /// This function will not panic in practice; `"LIST"` is always a
+    /// valid chunk id.
+    #[must_use]
+    pub fn into_chunk(self) -> Option<crate::ExtraChunk> {
+        let mut tags = vec![
+            ("IART", self.artist),
+            ("INAM", self.title),
+            ("ICMT", self.comment),
+        ];
+        tags.retain(|(_, v)| v.is_some());
+
+        if tags.is_empty() {
+            return None;
+        }
+
+        let mut data = b"INFO".to_vec();
+        for (tag_id, text) in tags {
+            let text = text.unwrap_or_default();
+            let mut text_bytes = text.into_bytes();
+            text_bytes.push(0);
+
+            data.extend_from_slice(tag_id.as_bytes());
+            #[allow(clippy::cast_possible_truncation)]
+            data.extend_from_slice(&(text_bytes.len() as u32).to_le_bytes());
+            data.extend_from_slice(&text_bytes);
+            if text_bytes.len() % 2 != 0 {
+                data.push(0);
+            }
+        }
+
+        Some((riff::ChunkId::new("LIST").unwrap(), data))
+    }
+}
+
+/// Reads the sample count out of a `fact` chunk in `chunks` (as returned by
+/// [`crate::read_with_chunks`]), if one is present.
+#[must_use]
+pub fn fact_sample_count(chunks: &[crate::ExtraChunk]) -> Option<u32> {
+    chunks.iter().find_map(|(id, data)| {
+        if id.as_str() == "fact" && data.len() >= 4 {
+            Some(u32::from_le_bytes([data[0], data[1], data[2], data[3]]))
+        } else {
+            None
+        }
+    })
+}
+
+/// Builds a `fact` chunk recording the given total sample count, for
+/// formats (such as compressed or float WAVE data) that are expected to
+/// carry one.
+///
+/// ## Panics
+///
+/// This function will not panic in practice; `"fact"` is always a valid
+/// chunk id.
+#[must_use]
+pub fn fact_chunk(sample_count: u32) -> crate::ExtraChunk {
+    (
+        riff::ChunkId::new("fact").unwrap(),
+        sample_count.to_le_bytes().to_vec(),
+    )
+}
+
+/// Iterates over the four-character-id/length-prefixed subchunks packed
+/// into a `LIST` chunk's body (after its four-byte list type).
+fn iter_sub_chunks(mut body: &[u8]) -> impl Iterator<Item = (riff::ChunkId, &[u8])> {
+    std::iter::from_fn(move || {
+        if body.len() < 8 {
+            return None;
+        }
+
+        let id = riff::ChunkId {
+            value: [body[0], body[1], body[2], body[3]],
+        };
+        let len = usize::try_from(u32::from_le_bytes([body[4], body[5], body[6], body[7]])).ok()?;
+        let data = body.get(8..8 + len)?;
+
+        let padded_len = len + (len % 2);
+        body = body.get(8 + padded_len..).unwrap_or(&[]);
+
+        Some((id, data))
+    })
+}
+
+fn strip_nul(data: &[u8]) -> &[u8] {
+    match data.iter().position(|&b| b == 0) {
+        Some(i) => &data[..i],
+        None => data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn info_tags_round_trip_through_a_list_chunk() {
+        let tags = InfoTags {
+            artist: Some("Artist".to_owned()),
+            title: Some("Title".to_owned()),
+            comment: None,
+        };
+
+        let (id, data) = tags.clone().into_chunk().unwrap();
+        assert_eq!(id.as_str(), "LIST");
+
+        let parsed = InfoTags::from_chunks(&[(id, data)]);
+        assert_eq!(parsed, tags);
+    }
+
+    #[test]
+    fn info_tags_into_chunk_is_none_when_all_fields_unset() {
+        assert_eq!(InfoTags::default().into_chunk(), None);
+    }
+
+    #[test]
+    fn info_tags_from_chunks_ignores_non_info_chunks() {
+        let other = (riff::ChunkId::new("fact").unwrap(), vec![1, 0, 0, 0]);
+        assert_eq!(InfoTags::from_chunks(&[other]), InfoTags::default());
+    }
+
+    #[test]
+    fn info_tags_round_trip_handles_odd_length_text() {
+        // "Bob" is 3 bytes (4 with the NUL terminator), forcing the
+        // sub-chunk padding byte to actually be exercised.
+        let tags = InfoTags {
+            artist: Some("Bob".to_owned()),
+            title: None,
+            comment: None,
+        };
+
+        let (id, data) = tags.clone().into_chunk().unwrap();
+        assert_eq!(InfoTags::from_chunks(&[(id, data)]), tags);
+    }
+
+    #[test]
+    fn iter_sub_chunks_stops_cleanly_on_a_truncated_sub_chunk() {
+        let mut body = Vec::new();
+        body.extend_from_slice(b"IART");
+        body.extend_from_slice(&5u32.to_le_bytes());
+        body.extend_from_slice(b"Bob"); // only 3 of the claimed 5 bytes follow
+
+        let parsed: Vec<_> = iter_sub_chunks(&body).collect();
+        assert!(parsed.is_empty());
+    }
+
+    #[test]
+    fn iter_sub_chunks_stops_cleanly_on_a_truncated_header() {
+        let body = [0x49, 0x41, 0x52, 0x54, 0x01, 0x00]; // "IART" + 2 of 4 length bytes
+        assert!(iter_sub_chunks(&body).next().is_none());
+    }
+
+    #[test]
+    fn fact_chunk_round_trips_through_fact_sample_count() {
+        let (id, data) = fact_chunk(12_345);
+        assert_eq!(id.as_str(), "fact");
+        assert_eq!(fact_sample_count(&[(id, data)]), Some(12_345));
+    }
+
+    #[test]
+    fn fact_sample_count_is_none_without_a_fact_chunk() {
+        assert_eq!(fact_sample_count(&[]), None);
+    }
+
+    #[test]
+    fn fact_sample_count_is_none_for_a_truncated_fact_chunk() {
+        let chunk = (riff::ChunkId::new("fact").unwrap(), vec![1, 2]);
+        assert_eq!(fact_sample_count(&[chunk]), None);
+    }
+}
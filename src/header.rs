@@ -0,0 +1,307 @@
+//! Contains the `Header` struct, describing the `fmt ` chunk of a wave file.
+
+use std::convert::TryFrom;
+
+/// The `WAVE_FORMAT_EXTENSIBLE` audio format tag, used when a `fmt ` chunk
+/// carries a [`HeaderExtension`] rather than encoding its format directly in
+/// `audio_format`.
+pub const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// The fixed tail of a `SubFormat` GUID, as found in an extensible `fmt `
+/// chunk. The first two bytes of the GUID hold the real `audio_format`
+/// instead, so together they spell out `{audio_format}-0000-0010-8000-00AA00389B71`.
+const SUBFORMAT_GUID_TAIL: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+];
+
+/// Describes the format of the audio data, as read from or written to the
+/// `fmt ` chunk of a wave file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// The audio format, stored as an integer. `1` indicates uncompressed
+    /// PCM data, while other values indicate some form of compression.
+    /// When `extension` is present, this is [`WAVE_FORMAT_EXTENSIBLE`]; use
+    /// [`Header::real_audio_format`] to get the effective format instead.
+    pub audio_format: u16,
+    /// The number of channels present in the audio data.
+    pub channel_count: u16,
+    /// The sampling rate of the audio data, in hertz.
+    pub sampling_rate: u32,
+    /// The number of bytes read per second, equivalent to
+    /// `sampling_rate * channel_count * bits_per_sample / 8`.
+    pub bytes_per_second: u32,
+    /// The number of bytes in a frame, i.e. one sample for every channel,
+    /// equivalent to `channel_count * bits_per_sample / 8`.
+    pub bytes_per_sample: u16,
+    /// The number of bits in a single sample.
+    pub bits_per_sample: u16,
+    /// The `WAVE_FORMAT_EXTENSIBLE` extension fields, present when the
+    /// `fmt ` chunk this `Header` came from (or will be written as) is in
+    /// the extensible form.
+    pub extension: Option<HeaderExtension>,
+    /// The number of samples encoded per compressed block, read from a
+    /// compressed (e.g. ADPCM) `fmt ` chunk's extension. `None` for
+    /// uncompressed formats, which have no notion of a block.
+    pub samples_per_block: Option<u16>,
+}
+
+/// The extra fields carried by an extensible (`WAVE_FORMAT_EXTENSIBLE`)
+/// `fmt ` chunk, on top of the fields already in [`Header`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HeaderExtension {
+    /// The number of bits actually meaningful within each
+    /// `bits_per_sample`-sized sample, which may be smaller than
+    /// `bits_per_sample` itself.
+    pub valid_bits_per_sample: u16,
+    /// A bitmask identifying which speaker position each channel maps to.
+    pub channel_mask: u32,
+    /// The real audio format, taken from the first two bytes of the
+    /// `SubFormat` GUID. `1` indicates uncompressed PCM data, while `3`
+    /// indicates IEEE float data.
+    pub sub_format: u16,
+}
+
+impl Header {
+    /// Creates a new Header object from values typically found in
+    /// hound/sox/ffmpeg. Fields such as `bytes_per_second` and
+    /// `bytes_per_sample` are derived from the given parameters. The
+    /// resulting `Header` has no extensible `fmt ` extension; see
+    /// [`Header::with_extension`] to add one.
+    #[must_use]
+    pub fn new(audio_format: u16, channel_count: u16, sampling_rate: u32, bits_per_sample: u16) -> Self {
+        let bytes_per_sample = channel_count * bits_per_sample / 8;
+
+        Header {
+            audio_format,
+            channel_count,
+            sampling_rate,
+            bytes_per_second: sampling_rate * u32::from(bytes_per_sample),
+            bytes_per_sample,
+            bits_per_sample,
+            extension: None,
+            samples_per_block: None,
+        }
+    }
+
+    /// Returns a copy of this `Header` carrying the given
+    /// `WAVE_FORMAT_EXTENSIBLE` extension fields. This also sets
+    /// `audio_format` to [`WAVE_FORMAT_EXTENSIBLE`], matching what parsing a
+    /// real extensible `fmt ` chunk back into a `Header` would produce; use
+    /// [`Header::real_audio_format`] to recover the original format.
+    #[must_use]
+    pub fn with_extension(mut self, extension: HeaderExtension) -> Self {
+        self.audio_format = WAVE_FORMAT_EXTENSIBLE;
+        self.extension = Some(extension);
+        self
+    }
+
+    /// The effective audio format: the `sub_format` of the `extension`, if
+    /// present, or `audio_format` otherwise.
+    #[must_use]
+    pub fn real_audio_format(&self) -> u16 {
+        self.extension.map_or(self.audio_format, |e| e.sub_format)
+    }
+
+    /// Serializes this `Header` into the raw bytes of a `fmt ` chunk,
+    /// choosing the extensible (40-byte) form when `extension` is set or
+    /// there are more than two channels, and the basic (16-byte) form
+    /// otherwise.
+    #[must_use]
+    pub fn to_fmt_chunk_bytes(&self) -> Vec<u8> {
+        let extension = self.extension.unwrap_or(HeaderExtension {
+            valid_bits_per_sample: self.bits_per_sample,
+            channel_mask: 0,
+            sub_format: self.audio_format,
+        });
+
+        if self.extension.is_none() && self.channel_count <= 2 {
+            let basic: [u8; 16] = (*self).into();
+            return basic.to_vec();
+        }
+
+        let mut v = vec![0u8; 40];
+
+        v[0..2].copy_from_slice(&WAVE_FORMAT_EXTENSIBLE.to_le_bytes());
+        v[2..4].copy_from_slice(&self.channel_count.to_le_bytes());
+        v[4..8].copy_from_slice(&self.sampling_rate.to_le_bytes());
+        v[8..12].copy_from_slice(&self.bytes_per_second.to_le_bytes());
+        v[12..14].copy_from_slice(&self.bytes_per_sample.to_le_bytes());
+        v[14..16].copy_from_slice(&self.bits_per_sample.to_le_bytes());
+        v[16..18].copy_from_slice(&22u16.to_le_bytes());
+        v[18..20].copy_from_slice(&extension.valid_bits_per_sample.to_le_bytes());
+        v[20..24].copy_from_slice(&extension.channel_mask.to_le_bytes());
+        v[24..26].copy_from_slice(&extension.sub_format.to_le_bytes());
+        v[26..40].copy_from_slice(&SUBFORMAT_GUID_TAIL);
+
+        v
+    }
+}
+
+impl TryFrom<&[u8]> for Header {
+    type Error = String;
+
+    /// Attempts to parse a `Header` out of the raw bytes of a `fmt ` chunk.
+    ///
+    /// If the chunk is 18 bytes or longer and carries a non-zero extension
+    /// size (`cbSize`), the extension is parsed as well. A full 40-byte
+    /// `WAVE_FORMAT_EXTENSIBLE` extension additionally yields a populated
+    /// [`HeaderExtension`] on the returned `Header`.
+    fn try_from(v: &[u8]) -> Result<Self, Self::Error> {
+        if v.len() < 16 {
+            return Err(format!(
+                "Received data is only {} bytes, expected at least 16",
+                v.len()
+            ));
+        }
+
+        let mut header = Header {
+            audio_format: u16::from_le_bytes([v[0], v[1]]),
+            channel_count: u16::from_le_bytes([v[2], v[3]]),
+            sampling_rate: u32::from_le_bytes([v[4], v[5], v[6], v[7]]),
+            bytes_per_second: u32::from_le_bytes([v[8], v[9], v[10], v[11]]),
+            bytes_per_sample: u16::from_le_bytes([v[12], v[13]]),
+            bits_per_sample: u16::from_le_bytes([v[14], v[15]]),
+            extension: None,
+            samples_per_block: None,
+        };
+
+        if v.len() < 18 {
+            return Ok(header);
+        }
+
+        let cb_size = u16::from_le_bytes([v[16], v[17]]);
+        if cb_size == 0 || v.len() < 18 + usize::from(cb_size) {
+            return Ok(header);
+        }
+
+        if header.audio_format == WAVE_FORMAT_EXTENSIBLE {
+            if cb_size >= 22 {
+                header.extension = Some(HeaderExtension {
+                    valid_bits_per_sample: u16::from_le_bytes([v[18], v[19]]),
+                    channel_mask: u32::from_le_bytes([v[20], v[21], v[22], v[23]]),
+                    sub_format: u16::from_le_bytes([v[24], v[25]]),
+                });
+            }
+        } else if cb_size >= 2 {
+            header.samples_per_block = Some(u16::from_le_bytes([v[18], v[19]]));
+        }
+
+        Ok(header)
+    }
+}
+
+impl From<Header> for [u8; 16] {
+    /// Converts the `Header` into the raw bytes of the basic (non-extensible)
+    /// form of a `fmt ` chunk. See [`Header::to_fmt_chunk_bytes`] for a
+    /// conversion that also supports the extensible form.
+    fn from(h: Header) -> Self {
+        let mut v = [0; 16];
+
+        v[0..2].copy_from_slice(&h.audio_format.to_le_bytes());
+        v[2..4].copy_from_slice(&h.channel_count.to_le_bytes());
+        v[4..8].copy_from_slice(&h.sampling_rate.to_le_bytes());
+        v[8..12].copy_from_slice(&h.bytes_per_second.to_le_bytes());
+        v[12..14].copy_from_slice(&h.bytes_per_sample.to_le_bytes());
+        v[14..16].copy_from_slice(&h.bits_per_sample.to_le_bytes());
+
+        v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_extension_sets_extensible_audio_format() {
+        let header = Header::new(1, 2, 44_100, 16).with_extension(HeaderExtension {
+            valid_bits_per_sample: 16,
+            channel_mask: 3,
+            sub_format: 1,
+        });
+
+        assert_eq!(header.audio_format, WAVE_FORMAT_EXTENSIBLE);
+        assert_eq!(header.real_audio_format(), 1);
+    }
+
+    #[test]
+    fn extensible_header_round_trips_through_fmt_chunk_bytes() {
+        let header = Header::new(1, 6, 48_000, 24).with_extension(HeaderExtension {
+            valid_bits_per_sample: 24,
+            channel_mask: 0x3F,
+            sub_format: 1,
+        });
+
+        let bytes = header.to_fmt_chunk_bytes();
+        let parsed = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(parsed, header);
+    }
+
+    #[test]
+    fn try_from_parses_basic_16_byte_chunk() {
+        let bytes: [u8; 16] = Header::new(1, 2, 44_100, 16).into();
+        let parsed = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(parsed, Header::new(1, 2, 44_100, 16));
+    }
+
+    #[test]
+    fn try_from_rejects_too_short_data() {
+        assert!(Header::try_from([0u8; 15].as_slice()).is_err());
+    }
+
+    #[test]
+    fn try_from_ignores_zero_cb_size() {
+        let mut bytes = Vec::from(<[u8; 16]>::from(Header::new(1, 2, 44_100, 16)));
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+
+        let parsed = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(parsed.extension, None);
+        assert_eq!(parsed.samples_per_block, None);
+    }
+
+    #[test]
+    fn try_from_parses_samples_per_block_for_compressed_formats() {
+        let mut bytes = Vec::from(<[u8; 16]>::from(Header::new(2, 1, 8_000, 4)));
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&512u16.to_le_bytes());
+
+        let parsed = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(parsed.samples_per_block, Some(512));
+        assert_eq!(parsed.extension, None);
+    }
+
+    #[test]
+    fn try_from_parses_full_extensible_extension() {
+        let mut bytes = Vec::from(<[u8; 16]>::from(Header {
+            audio_format: WAVE_FORMAT_EXTENSIBLE,
+            channel_count: 6,
+            sampling_rate: 48_000,
+            bytes_per_second: 48_000 * 6 * 3,
+            bytes_per_sample: 18,
+            bits_per_sample: 24,
+            extension: None,
+            samples_per_block: None,
+        }));
+        bytes.extend_from_slice(&22u16.to_le_bytes());
+        bytes.extend_from_slice(&24u16.to_le_bytes());
+        bytes.extend_from_slice(&0x3Fu32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&SUBFORMAT_GUID_TAIL);
+
+        let parsed = Header::try_from(bytes.as_slice()).unwrap();
+
+        assert_eq!(
+            parsed.extension,
+            Some(HeaderExtension {
+                valid_bits_per_sample: 24,
+                channel_mask: 0x3F,
+                sub_format: 1,
+            })
+        );
+        assert_eq!(parsed.real_audio_format(), 1);
+    }
+}
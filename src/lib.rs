@@ -1,10 +1,22 @@
 //! # WAV
 //!
 //! This is a crate for reading in and writing out wave files. It supports bit-
-//! depths of 8, 16, and 24 bits, any number of channels, and uncompressed PCM
-//! data. Unfortunately other types of data format (e.g. compressed WAVE files)
-//! are not supported. There is also no support for any metadata chunks or any
-//! chunks other than the "fmt " and "data" chunks.
+//! depths of 8, 16, 24, and 32 bits, any number of channels, and uncompressed
+//! PCM data, as well as 32- and 64-bit IEEE float data. `WAVE_FORMAT_EXTENSIBLE`
+//! `fmt ` chunks are also understood; see [`Header::real_audio_format`] and
+//! [`header::HeaderExtension`]. [`read`] additionally decodes Microsoft and
+//! IMA/DVI ADPCM data (see the [`adpcm`] module) into [`BitDepth::Sixteen`];
+//! other compressed formats are not supported. Since ADPCM blocks are
+//! padded to `block_align`, the final block may decode a few samples of
+//! trailing padding past a file's true sample count; [`read_with_chunks`]
+//! trims this using the `fact` chunk's sample count when one is present,
+//! but [`read`] has no access to sibling chunks and can't.
+//! [`read`] and [`write()`] only look at the "fmt " and "data" chunks; use
+//! [`read_with_chunks`] and [`write_with_chunks`] to preserve other chunks,
+//! such as `LIST`/`INFO` metadata or a `fact` chunk, across a round-trip.
+//! See the [`chunks`] module for typed helpers for those chunks.
+//! [`BitDepth`] also has conversion and [resampling][`BitDepth::resample`]
+//! helpers for normalizing decoded audio between depths and sample rates.
 //!
 //! ## Example
 //!
@@ -22,11 +34,10 @@
 //! # }
 //! ```
 
-#![deny(broken_intra_doc_links)]
+#![deny(rustdoc::broken_intra_doc_links)]
 #![warn(clippy::all)]
 #![warn(clippy::pedantic)]
 
-use riff;
 use std::{
     convert::TryFrom,
     io::{self, Read, Write},
@@ -39,7 +50,21 @@ pub mod bit_depth;
 pub use bit_depth::BitDepth;
 
 mod tuple_iterator;
-use tuple_iterator::{PairIter, TripletIter};
+use tuple_iterator::{PairIter, QuadIter, TripletIter};
+
+pub mod reader;
+pub use reader::Reader;
+
+pub mod writer;
+pub use writer::Writer;
+
+pub mod chunks;
+
+pub mod adpcm;
+
+/// A raw, unrecognized RIFF chunk preserved verbatim by [`read_with_chunks`]
+/// and re-emitted by [`write_with_chunks`].
+pub type ExtraChunk = (riff::ChunkId, Vec<u8>);
 
 /// Reads in the given `reader` and attempts to extract the audio data and
 /// header from it.
@@ -49,7 +74,8 @@ use tuple_iterator::{PairIter, TripletIter};
 /// This function fails under the following circumstances:
 /// * Any error occurring from the `reader` parameter during reading.
 /// * The data isn't RIFF data.
-/// * The wave header specifies a compressed data format.
+/// * The wave header specifies a data format other than PCM, IEEE float,
+///   or ADPCM.
 /// * The wave header specifies an unsupported bit-depth.
 /// * The wave data is malformed, or otherwise couldn't be parsed into samples.
 pub fn read<R>(reader: &mut R) -> io::Result<(Header, BitDepth)>
@@ -72,21 +98,142 @@ where
 ///
 /// This function fails under the following circumstances:
 /// * Any error occurring from the `writer` parameter during writing.
-/// * The given BitDepth is `BitDepth::Empty`.
+/// * The given `BitDepth` is `BitDepth::Empty`.
+///
+/// ## Panics
+///
+/// This function will not panic in practice; the internally constructed
+/// `"WAVE"`/`"fmt "`/`"data"` chunk ids are always valid.
 ///
 /// [0]: riff::write_chunk
 pub fn write<W>(header: Header, track: &BitDepth, writer: &mut W) -> std::io::Result<()>
 where
     W: Write + io::Seek
+{
+    write_with_chunks(header, track, &[], writer)
+}
+
+/// Reads in the given `reader`, like [`read`], but additionally returns
+/// every chunk other than `fmt ` and `data` verbatim, so that metadata such
+/// as `LIST`/`INFO` tags or a `fact` chunk survives a read/write
+/// round-trip. See [`chunks`] for typed helpers that work with the
+/// returned chunks.
+///
+/// ## Errors
+///
+/// This function fails under the same circumstances as [`read`].
+///
+/// ## Panics
+///
+/// This function will not panic in practice; see [`write()`].
+pub fn read_with_chunks<R>(
+    reader: &mut R,
+) -> io::Result<(Header, BitDepth, Vec<ExtraChunk>)>
+where
+    R: Read + io::Seek,
+{
+    let wav = verify_wav_file(reader)?;
+
+    let mut all_chunks = Vec::new();
+    for c in wav.iter(reader) {
+        all_chunks.push(c?);
+    }
+
+    let mut header = None;
+    let mut data_bytes = None;
+    let mut extra_chunks = Vec::new();
+
+    for c in all_chunks {
+        match c.id().as_str() {
+            "fmt " => header = Some(parse_header(c.read_contents(reader)?.as_slice())?),
+            "data" => data_bytes = Some(c.read_contents(reader)?),
+            _ => extra_chunks.push((c.id(), c.read_contents(reader)?)),
+        }
+    }
+
+    let header = header.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "RIFF data is missing the \"fmt \" chunk, aborting",
+        )
+    })?;
+    let data_bytes =
+        data_bytes.ok_or_else(|| io::Error::other("Could not parse audio data"))?;
+
+    let mut track = decode_data(&header, data_bytes)?;
+    trim_adpcm_padding(&header, &mut track, &extra_chunks);
+
+    Ok((header, track, extra_chunks))
+}
+
+/// ADPCM blocks are padded to `block_align`, so the last block of a `data`
+/// chunk may decode a few samples' worth of trailing padding/decay past the
+/// file's true sample count. If a `fact` chunk is present, as it usually is
+/// for compressed formats, trim `track` back down to the sample count it
+/// records.
+fn trim_adpcm_padding(header: &Header, track: &mut BitDepth, extra_chunks: &[ExtraChunk]) {
+    if !matches!(
+        header.real_audio_format(),
+        adpcm::WAVE_FORMAT_ADPCM | adpcm::WAVE_FORMAT_DVI_ADPCM
+    ) {
+        return;
+    }
+
+    let (BitDepth::Sixteen(samples), Some(sample_count)) =
+        (track, chunks::fact_sample_count(extra_chunks))
+    else {
+        return;
+    };
+
+    let total = usize::try_from(sample_count).unwrap_or(usize::MAX)
+        * usize::from(header.channel_count.max(1));
+    samples.truncate(total);
+}
+
+/// Writes the given wav data to the given `writer`, like [`write()`], but
+/// additionally re-emits the given `extra_chunks` (e.g. as returned by
+/// [`read_with_chunks`]) after the `fmt ` and `data` chunks.
+///
+/// ## Errors
+///
+/// This function fails under the same circumstances as [`write()`].
+///
+/// ## Panics
+///
+/// This function will not panic in practice; see [`write()`].
+pub fn write_with_chunks<W>(
+    header: Header,
+    track: &BitDepth,
+    extra_chunks: &[ExtraChunk],
+    writer: &mut W,
+) -> std::io::Result<()>
+where
+    W: Write + io::Seek,
 {
     let w_id = riff::ChunkId::new("WAVE").unwrap();
 
     let h_id = riff::ChunkId::new("fmt ").unwrap();
-    let h_vec: [u8; 16] = header.into();
-    let h_dat = riff::ChunkContents::Data(h_id, Vec::from(&h_vec[0..16]));
+    let h_dat = riff::ChunkContents::Data(h_id, header.to_fmt_chunk_bytes());
 
     let d_id = riff::ChunkId::new("data").unwrap();
-    let d_vec = match track {
+    let d_dat = riff::ChunkContents::Data(d_id, encode_data(track)?);
+
+    let mut children = vec![h_dat, d_dat];
+    children.extend(
+        extra_chunks
+            .iter()
+            .map(|(id, data)| riff::ChunkContents::Data(*id, data.clone())),
+    );
+
+    let r = riff::ChunkContents::Children(riff::RIFF_ID, w_id, children);
+
+    r.write(writer)?;
+
+    Ok(())
+}
+
+fn encode_data(track: &BitDepth) -> io::Result<Vec<u8>> {
+    Ok(match track {
         BitDepth::Eight(v) => v.clone(),
         BitDepth::Sixteen(v) => v.iter()
             .flat_map(
@@ -104,20 +251,29 @@ where
                 }
             )
             .collect::<Vec<_>>(),
-        _ => return Err(
-            std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Empty audio data given",
+        BitDepth::ThirtyTwo(v) => v.iter()
+            .flat_map(
+                |s| {
+                    let v = s.to_le_bytes();
+                    QuadIter::new((v[0], v[1], v[2], v[3]))
+                }
             )
+            .collect::<Vec<_>>(),
+        BitDepth::ThirtyTwoFloat(v) => v.iter()
+            .flat_map(
+                |s| {
+                    let v = s.to_le_bytes();
+                    QuadIter::new((v[0], v[1], v[2], v[3]))
+                }
+            )
+            .collect::<Vec<_>>(),
+        BitDepth::SixtyFourFloat(v) => v.iter()
+            .flat_map(|s| s.to_le_bytes().to_vec())
+            .collect::<Vec<_>>(),
+        BitDepth::Empty => return Err(
+            std::io::Error::other("Empty audio data given")
         ),
-    };
-    let d_dat = riff::ChunkContents::Data(d_id, d_vec);
-
-    let r = riff::ChunkContents::Children(riff::RIFF_ID.clone(), w_id, vec![h_dat, d_dat]);
-
-    r.write(writer)?;
-
-    Ok(())
+    })
 }
 
 fn read_header<R>(reader: &mut R) -> io::Result<Header>
@@ -127,26 +283,9 @@ where
     let wav = verify_wav_file(reader)?;
 
     for c in wav.iter(reader) {
+        let c = c?;
         if c.id().as_str() == "fmt " {
-            // Read header contents
-            let header_bytes = c.read_contents(reader)?;
-            let header = Header::try_from(header_bytes.as_slice())
-                .map_err(
-                    |e| io::Error::new(
-                        io::ErrorKind::Other,
-                        e
-                    )
-                )?;
-
-            // Return error if not using PCM
-            if header.audio_format != 1 {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Unsupported data format, data is not in uncompressed PCM format, aborting",
-                ));
-            }
-
-            return Ok(header);
+            return parse_header(c.read_contents(reader)?.as_slice());
         }
     }
 
@@ -165,43 +304,74 @@ where
     let wav = verify_wav_file(reader)?;
 
     for c in wav.iter(reader) {
+        let c = c?;
         if c.id().as_str() == "data" {
-            // Read data contents
-            let data_bytes = c.read_contents(reader)?;
-
-            return Ok(
-                match header.bits_per_sample {
-                    8 => BitDepth::Eight(data_bytes),
-                    16 => BitDepth::Sixteen({
-                        let mut tmpv = Vec::with_capacity(data_bytes.len() / 2);
-                        tmpv.extend(data_bytes.chunks_exact(2).map(|i| i16::from_le_bytes([i[0], i[1]])));
-                        tmpv
-                    }),
-                    24 => BitDepth::TwentyFour({
-                        let mut tmpv = Vec::with_capacity(data_bytes.len() / 3);
-                        tmpv.extend(data_bytes.chunks_exact(3).map(|i| i32::from_le_bytes([0, i[0], i[1], i[2]])));
-                        tmpv
-                    }),
-                    _ => {
-                        return Err(io::Error::new(
-                            io::ErrorKind::Other,
-                            "Unsupported bit depth",
-                        ));
-                    }
-                }
-            );
+            return decode_data(header, c.read_contents(reader)?);
         }
     }
 
-    Err(
-        io::Error::new(
-            io::ErrorKind::Other,
-            "Could not parse audio data",
-        )
+    Err(io::Error::other("Could not parse audio data"))
+}
+
+fn parse_header(header_bytes: &[u8]) -> io::Result<Header> {
+    let header = Header::try_from(header_bytes).map_err(io::Error::other)?;
+
+    // Return error if not using a format this crate knows how to decode.
+    let fmt = header.real_audio_format();
+    if fmt != 1 && fmt != 3 && fmt != adpcm::WAVE_FORMAT_ADPCM && fmt != adpcm::WAVE_FORMAT_DVI_ADPCM {
+        return Err(io::Error::other(
+            "Unsupported data format, data is not in uncompressed PCM, IEEE float, or ADPCM format, aborting",
+        ));
+    }
+
+    Ok(header)
+}
+
+fn decode_data(header: &Header, data_bytes: Vec<u8>) -> io::Result<BitDepth> {
+    match header.real_audio_format() {
+        adpcm::WAVE_FORMAT_ADPCM => return adpcm::decode_ms(header, &data_bytes),
+        adpcm::WAVE_FORMAT_DVI_ADPCM => return adpcm::decode_ima(header, &data_bytes),
+        _ => {}
+    }
+
+    Ok(
+        match (header.real_audio_format(), header.bits_per_sample) {
+            (1, 8) => BitDepth::Eight(data_bytes),
+            (1, 16) => BitDepth::Sixteen({
+                let mut tmpv = Vec::with_capacity(data_bytes.len() / 2);
+                tmpv.extend(data_bytes.chunks_exact(2).map(|i| i16::from_le_bytes([i[0], i[1]])));
+                tmpv
+            }),
+            (1, 24) => BitDepth::TwentyFour({
+                let mut tmpv = Vec::with_capacity(data_bytes.len() / 3);
+                tmpv.extend(data_bytes.chunks_exact(3).map(|i| i32::from_le_bytes([0, i[0], i[1], i[2]])));
+                tmpv
+            }),
+            (1, 32) => BitDepth::ThirtyTwo({
+                let mut tmpv = Vec::with_capacity(data_bytes.len() / 4);
+                tmpv.extend(data_bytes.chunks_exact(4).map(|i| i32::from_le_bytes([i[0], i[1], i[2], i[3]])));
+                tmpv
+            }),
+            (3, 32) => BitDepth::ThirtyTwoFloat({
+                let mut tmpv = Vec::with_capacity(data_bytes.len() / 4);
+                tmpv.extend(data_bytes.chunks_exact(4).map(|i| f32::from_le_bytes([i[0], i[1], i[2], i[3]])));
+                tmpv
+            }),
+            (3, 64) => BitDepth::SixtyFourFloat({
+                let mut tmpv = Vec::with_capacity(data_bytes.len() / 8);
+                tmpv.extend(data_bytes.chunks_exact(8).map(|i| {
+                    f64::from_le_bytes([i[0], i[1], i[2], i[3], i[4], i[5], i[6], i[7]])
+                }));
+                tmpv
+            }),
+            _ => {
+                return Err(io::Error::other("Unsupported bit depth"));
+            }
+        }
     )
 }
 
-fn verify_wav_file<R>(reader: &mut R) -> io::Result<riff::Chunk>
+pub(crate) fn verify_wav_file<R>(reader: &mut R) -> io::Result<riff::Chunk>
 where
     R: Read + io::Seek,
 {
@@ -209,14 +379,56 @@ where
 
     let form_type = wav.read_type(reader)?;
 
-    if form_type.as_str() != "WAVE" {
-        Err(
-            io::Error::new(
-                io::ErrorKind::Other,
-                "RIFF file type not \"WAVE\"",
-            )
-        )
-    } else {
+    if form_type.as_str() == "WAVE" {
         Ok(wav)
+    } else {
+        Err(io::Error::other("RIFF file type not \"WAVE\""))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trim_adpcm_padding_trims_to_fact_sample_count() {
+        let header = Header {
+            audio_format: adpcm::WAVE_FORMAT_ADPCM,
+            channel_count: 2,
+            sampling_rate: 8_000,
+            bytes_per_second: 8_000,
+            bytes_per_sample: 4,
+            bits_per_sample: 4,
+            extension: None,
+            samples_per_block: Some(4),
+        };
+
+        // Block-padded decode produced 4 interleaved samples' worth of
+        // frames, but the file only has 3 true per-channel samples.
+        let mut track = BitDepth::Sixteen(vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let extra_chunks = vec![chunks::fact_chunk(3)];
+
+        trim_adpcm_padding(&header, &mut track, &extra_chunks);
+
+        assert_eq!(track, BitDepth::Sixteen(vec![1, 2, 3, 4, 5, 6]));
+    }
+
+    #[test]
+    fn trim_adpcm_padding_is_a_no_op_without_a_fact_chunk() {
+        let header = Header {
+            audio_format: adpcm::WAVE_FORMAT_ADPCM,
+            channel_count: 1,
+            sampling_rate: 8_000,
+            bytes_per_second: 8_000,
+            bytes_per_sample: 4,
+            bits_per_sample: 4,
+            extension: None,
+            samples_per_block: Some(4),
+        };
+
+        let mut track = BitDepth::Sixteen(vec![1, 2, 3, 4]);
+        trim_adpcm_padding(&header, &mut track, &[]);
+
+        assert_eq!(track, BitDepth::Sixteen(vec![1, 2, 3, 4]));
     }
 }
@@ -0,0 +1,262 @@
+//! Contains the [`Reader`] struct, a streaming alternative to [`crate::read`]
+//! for working with large wave files without buffering the whole `data`
+//! chunk into memory.
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::{verify_wav_file, Header};
+
+/// Lazily pulls samples out of a wave file's `data` chunk, one at a time.
+///
+/// Unlike [`crate::read`], which decodes the entire `data` chunk into a
+/// [`crate::BitDepth`] up front, `Reader::new` only parses the RIFF/`fmt `
+/// header eagerly; samples are then read from the underlying `reader` as
+/// the returned iterator is driven, so memory use stays bounded regardless
+/// of file size. Samples are always widened to `i32`, regardless of the
+/// underlying bit depth, in the same way each bit depth is already stored
+/// within [`crate::BitDepth`] (e.g. 8-bit samples stay unsigned, 24-bit
+/// samples are not sign-extended).
+///
+/// Only uncompressed PCM data (`audio_format == 1`) at 8, 16, 24, or 32
+/// bits per sample is supported.
+pub struct Reader<R> {
+    header: Header,
+    inner: R,
+    data_end: u64,
+    bytes_per_sample: u64,
+}
+
+impl<R> Reader<R>
+where
+    R: Read + Seek,
+{
+    /// Parses the RIFF/`fmt ` header out of `reader` and seeks it to the
+    /// start of the `data` chunk's contents, ready to be driven as an
+    /// iterator.
+    ///
+    /// ## Errors
+    ///
+    /// This function fails under the following circumstances:
+    /// * Any error occurring from the `reader` parameter during reading.
+    /// * The data isn't RIFF data.
+    /// * The wave header specifies a compressed or unsupported data format.
+    /// * The wave header specifies an unsupported bit-depth.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let header = {
+            let wav = verify_wav_file(&mut reader)?;
+            let mut found = None;
+
+            for c in wav.iter(&mut reader) {
+                let c = c?;
+                if c.id().as_str() == "fmt " {
+                    let header_bytes = c.read_contents(&mut reader)?;
+                    found = Some(Header::try_from(header_bytes.as_slice()).map_err(io::Error::other)?);
+                    break;
+                }
+            }
+
+            found.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "RIFF data is missing the \"fmt \" chunk, aborting",
+                )
+            })?
+        };
+
+        if header.real_audio_format() != 1 {
+            return Err(io::Error::other(
+                "Unsupported data format, data is not in uncompressed PCM format, aborting",
+            ));
+        }
+
+        let (data_start, data_len) = {
+            let wav = verify_wav_file(&mut reader)?;
+            let mut found = None;
+
+            for c in wav.iter(&mut reader) {
+                let c = c?;
+                if c.id().as_str() == "data" {
+                    found = Some((c.offset() + 8, u64::from(c.len())));
+                    break;
+                }
+            }
+
+            found.ok_or_else(|| io::Error::other("Could not parse audio data"))?
+        };
+
+        let bytes_per_sample = match header.bits_per_sample {
+            8 | 16 | 24 | 32 => u64::from(header.bits_per_sample) / 8,
+            _ => return Err(io::Error::other("Unsupported bit depth")),
+        };
+
+        reader.seek(SeekFrom::Start(data_start))?;
+
+        Ok(Reader {
+            header,
+            inner: reader,
+            data_end: data_start + data_len,
+            bytes_per_sample,
+        })
+    }
+
+    /// The wave header that was parsed out of the underlying reader.
+    #[must_use]
+    pub fn header(&self) -> Header {
+        self.header
+    }
+}
+
+impl<R> Iterator for Reader<R>
+where
+    R: Read + Seek,
+{
+    type Item = io::Result<i32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let pos = match self.inner.stream_position() {
+            Ok(pos) => pos,
+            Err(e) => return Some(Err(e)),
+        };
+
+        if pos + self.bytes_per_sample > self.data_end {
+            return None;
+        }
+
+        let mut buf = [0u8; 4];
+        let bytes_per_sample = match usize::try_from(self.bytes_per_sample) {
+            Ok(n) => n,
+            Err(e) => return Some(Err(io::Error::other(e))),
+        };
+        Some(
+            self.inner
+                .read_exact(&mut buf[..bytes_per_sample])
+                .map(|()| match self.bytes_per_sample {
+                    1 => i32::from(buf[0]),
+                    2 => i32::from(i16::from_le_bytes([buf[0], buf[1]])),
+                    3 => i32::from_le_bytes([0, buf[0], buf[1], buf[2]]),
+                    _ => i32::from_le_bytes(buf),
+                }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::BitDepth;
+
+    /// Hand-assembles a minimal `RIFF`/`WAVE` file out of raw `fmt ` and
+    /// `data` chunk bytes, for exercising cases `crate::write` can't
+    /// produce (e.g. unsupported formats/bit depths, truncated data).
+    fn build_wav(fmt_bytes: &[u8], data_bytes: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(b"WAVE");
+
+        body.extend_from_slice(b"fmt ");
+        body.extend_from_slice(&u32::try_from(fmt_bytes.len()).unwrap().to_le_bytes());
+        body.extend_from_slice(fmt_bytes);
+        if !fmt_bytes.len().is_multiple_of(2) {
+            body.push(0);
+        }
+
+        body.extend_from_slice(b"data");
+        body.extend_from_slice(&u32::try_from(data_bytes.len()).unwrap().to_le_bytes());
+        body.extend_from_slice(data_bytes);
+        if !data_bytes.len().is_multiple_of(2) {
+            body.push(0);
+        }
+
+        let mut out = Vec::new();
+        out.extend_from_slice(b"RIFF");
+        out.extend_from_slice(&u32::try_from(body.len()).unwrap().to_le_bytes());
+        out.extend_from_slice(&body);
+        out
+    }
+
+    #[test]
+    fn round_trips_sixteen_bit_stereo_samples() {
+        let header = Header::new(1, 2, 44_100, 16);
+        let track = BitDepth::Sixteen(vec![-1000, 1000, 2, -2]);
+
+        let mut buf = Cursor::new(Vec::new());
+        crate::write(header, &track, &mut buf).unwrap();
+        buf.set_position(0);
+
+        let reader = Reader::new(&mut buf).unwrap();
+        assert_eq!(reader.header(), header);
+
+        let samples: io::Result<Vec<i32>> = reader.collect();
+        assert_eq!(samples.unwrap(), vec![-1000, 1000, 2, -2]);
+    }
+
+    #[test]
+    fn eight_bit_samples_stay_unsigned() {
+        let header = Header::new(1, 1, 8_000, 8);
+        let track = BitDepth::Eight(vec![0, 128, 255]);
+
+        let mut buf = Cursor::new(Vec::new());
+        crate::write(header, &track, &mut buf).unwrap();
+        buf.set_position(0);
+
+        let samples: io::Result<Vec<i32>> = Reader::new(&mut buf).unwrap().collect();
+        assert_eq!(samples.unwrap(), vec![0, 128, 255]);
+    }
+
+    #[test]
+    fn twenty_four_bit_samples_round_trip_without_extra_sign_extension() {
+        let header = Header::new(1, 1, 48_000, 24);
+        // Stored (and read back) left-shifted by 8 bits within the i32, as
+        // every 24-bit sample in this crate is; the bottom byte is simply
+        // zero-filled rather than the top byte being sign-extended.
+        let track = BitDepth::TwentyFour(vec![-256]);
+
+        let mut buf = Cursor::new(Vec::new());
+        crate::write(header, &track, &mut buf).unwrap();
+        buf.set_position(0);
+
+        let samples: io::Result<Vec<i32>> = Reader::new(&mut buf).unwrap().collect();
+        assert_eq!(samples.unwrap(), vec![-256]);
+    }
+
+    #[test]
+    fn thirty_two_bit_samples_round_trip() {
+        let header = Header::new(1, 1, 48_000, 32);
+        let track = BitDepth::ThirtyTwo(vec![i32::MIN, i32::MAX, 0]);
+
+        let mut buf = Cursor::new(Vec::new());
+        crate::write(header, &track, &mut buf).unwrap();
+        buf.set_position(0);
+
+        let samples: io::Result<Vec<i32>> = Reader::new(&mut buf).unwrap().collect();
+        assert_eq!(samples.unwrap(), vec![i32::MIN, i32::MAX, 0]);
+    }
+
+    #[test]
+    fn stops_cleanly_on_a_trailing_partial_sample() {
+        let fmt_bytes: [u8; 16] = Header::new(1, 1, 8_000, 16).into();
+        // One full 16-bit sample, then one leftover byte short of another.
+        let wav = build_wav(&fmt_bytes, &[0x34, 0x12, 0xFF]);
+
+        let samples: io::Result<Vec<i32>> = Reader::new(&mut Cursor::new(wav)).unwrap().collect();
+        assert_eq!(samples.unwrap(), vec![0x1234]);
+    }
+
+    #[test]
+    fn rejects_compressed_formats() {
+        let fmt_bytes: [u8; 16] = Header::new(2, 1, 8_000, 4).into();
+        let wav = build_wav(&fmt_bytes, &[0x00]);
+
+        assert!(Reader::new(&mut Cursor::new(wav)).is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_bit_depths() {
+        let fmt_bytes: [u8; 16] = Header::new(1, 1, 8_000, 12).into();
+        let wav = build_wav(&fmt_bytes, &[0x00]);
+
+        assert!(Reader::new(&mut Cursor::new(wav)).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+//! Small helper iterators used to flatten fixed-size tuples of bytes back
+//! into a flat byte stream when writing sample data out via `flat_map`.
+
+/// Iterates over the two elements of a pair, in order.
+pub(crate) struct PairIter<T> {
+    pair: (T, T),
+    idx: u8,
+}
+
+impl<T> PairIter<T> {
+    pub fn new(pair: (T, T)) -> Self {
+        PairIter { pair, idx: 0 }
+    }
+}
+
+impl<T: Copy> Iterator for PairIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let item = match self.idx {
+            0 => Some(self.pair.0),
+            1 => Some(self.pair.1),
+            _ => None,
+        };
+        self.idx += 1;
+        item
+    }
+}
+
+/// Iterates over the four elements of a quadruplet, in order.
+pub(crate) struct QuadIter<T> {
+    quad: (T, T, T, T),
+    idx: u8,
+}
+
+impl<T> QuadIter<T> {
+    pub fn new(quad: (T, T, T, T)) -> Self {
+        QuadIter { quad, idx: 0 }
+    }
+}
+
+impl<T: Copy> Iterator for QuadIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let item = match self.idx {
+            0 => Some(self.quad.0),
+            1 => Some(self.quad.1),
+            2 => Some(self.quad.2),
+            3 => Some(self.quad.3),
+            _ => None,
+        };
+        self.idx += 1;
+        item
+    }
+}
+
+/// Iterates over the three elements of a triplet, in order.
+pub(crate) struct TripletIter<T> {
+    triplet: (T, T, T),
+    idx: u8,
+}
+
+impl<T> TripletIter<T> {
+    pub fn new(triplet: (T, T, T)) -> Self {
+        TripletIter { triplet, idx: 0 }
+    }
+}
+
+impl<T: Copy> Iterator for TripletIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let item = match self.idx {
+            0 => Some(self.triplet.0),
+            1 => Some(self.triplet.1),
+            2 => Some(self.triplet.2),
+            _ => None,
+        };
+        self.idx += 1;
+        item
+    }
+}
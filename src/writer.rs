@@ -0,0 +1,155 @@
+//! Contains the [`Writer`] struct, a streaming alternative to [`crate::write`]
+//! for emitting large wave files without buffering the whole `data` chunk
+//! into memory.
+
+use std::convert::TryFrom;
+use std::io::{self, Seek, SeekFrom, Write};
+
+use crate::Header;
+
+/// Writes samples to a wave file one at a time, back-patching the RIFF and
+/// `data` chunk sizes once writing is finished.
+///
+/// `Writer::new` writes the RIFF/`fmt `/`data` chunk headers up front, using
+/// placeholder sizes for the chunks whose length isn't yet known. Samples
+/// are then accepted incrementally through [`Writer::write_sample`], and
+/// [`Writer::finalize`] seeks back to patch in the real sizes. This lets
+/// multi-gigabyte recordings be written with bounded memory.
+///
+/// Only uncompressed PCM data (`audio_format == 1`) at 8, 16, 24, or 32
+/// bits per sample is supported.
+pub struct Writer<W> {
+    inner: W,
+    header: Header,
+    data_bytes_written: u32,
+    riff_len_pos: u64,
+    data_len_pos: u64,
+}
+
+impl<W> Writer<W>
+where
+    W: Write + Seek,
+{
+    /// Writes the RIFF/`fmt `/`data` chunk headers to `writer`, using the
+    /// given `header` for the `fmt ` chunk, and returns a `Writer` ready to
+    /// accept samples.
+    ///
+    /// ## Errors
+    ///
+    /// This function fails under the following circumstances:
+    /// * Any error occurring from the `writer` parameter during writing.
+    /// * The header specifies an unsupported bit depth.
+    pub fn new(mut writer: W, header: Header) -> io::Result<Self> {
+        if !matches!(header.bits_per_sample, 8 | 16 | 24 | 32) {
+            return Err(io::Error::other("Unsupported bit depth"));
+        }
+
+        writer.write_all(&riff::RIFF_ID.value)?;
+        let riff_len_pos = writer.stream_position()?;
+        writer.write_all(&[0u8; 4])?;
+        writer.write_all(b"WAVE")?;
+
+        writer.write_all(b"fmt ")?;
+        let fmt_bytes = header.to_fmt_chunk_bytes();
+        let fmt_len = u32::try_from(fmt_bytes.len()).map_err(io::Error::other)?;
+        writer.write_all(&fmt_len.to_le_bytes())?;
+        writer.write_all(&fmt_bytes)?;
+
+        writer.write_all(b"data")?;
+        let data_len_pos = writer.stream_position()?;
+        writer.write_all(&[0u8; 4])?;
+
+        Ok(Writer {
+            inner: writer,
+            header,
+            data_bytes_written: 0,
+            riff_len_pos,
+            data_len_pos,
+        })
+    }
+
+    /// Writes a single sample, truncated to the bit depth given to
+    /// [`Writer::new`].
+    ///
+    /// ## Errors
+    ///
+    /// This function fails if the underlying `writer` fails during writing.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn write_sample(&mut self, sample: i32) -> io::Result<()> {
+        let bytes_written = match self.header.bits_per_sample {
+            8 => {
+                self.inner.write_all(&[sample as u8])?;
+                1
+            }
+            16 => {
+                self.inner.write_all(&(sample as i16).to_le_bytes())?;
+                2
+            }
+            24 => {
+                self.inner.write_all(&sample.to_le_bytes()[0..3])?;
+                3
+            }
+            _ => {
+                self.inner.write_all(&sample.to_le_bytes())?;
+                4
+            }
+        };
+
+        self.data_bytes_written += bytes_written;
+
+        Ok(())
+    }
+
+    /// Back-patches the RIFF and `data` chunk sizes now that every sample
+    /// has been written.
+    ///
+    /// ## Errors
+    ///
+    /// This function fails if the underlying `writer` fails during seeking
+    /// or writing.
+    pub fn finalize(mut self) -> io::Result<()> {
+        if !self.data_bytes_written.is_multiple_of(2) {
+            self.inner.write_all(&[0])?;
+        }
+
+        let end_pos = self.inner.stream_position()?;
+
+        self.inner.seek(SeekFrom::Start(self.data_len_pos))?;
+        self.inner.write_all(&self.data_bytes_written.to_le_bytes())?;
+
+        let riff_len = u32::try_from(end_pos - self.riff_len_pos - 4)
+            .map_err(io::Error::other)?;
+        self.inner.seek(SeekFrom::Start(self.riff_len_pos))?;
+        self.inner.write_all(&riff_len.to_le_bytes())?;
+
+        self.inner.seek(SeekFrom::Start(end_pos))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::header::HeaderExtension;
+
+    #[test]
+    fn new_emits_extensible_fmt_chunk_for_extended_headers() {
+        let header = Header::new(1, 6, 48_000, 16).with_extension(HeaderExtension {
+            valid_bits_per_sample: 16,
+            channel_mask: 0x3F,
+            sub_format: 1,
+        });
+
+        let mut buf = Cursor::new(Vec::new());
+        let writer = Writer::new(&mut buf, header).unwrap();
+        writer.finalize().unwrap();
+
+        let bytes = buf.into_inner();
+        let fmt_len = u32::from_le_bytes([bytes[16], bytes[17], bytes[18], bytes[19]]);
+        assert_eq!(fmt_len, 40, "extensible fmt chunk must be emitted, not the basic 16-byte form");
+        assert_eq!(&bytes[20..22], &header.to_fmt_chunk_bytes()[0..2]);
+    }
+}